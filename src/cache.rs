@@ -4,8 +4,10 @@ use std::num::NonZeroUsize;
 use std::time::{Duration, Instant};
 
 pub struct Cache {
-    cache: LruCache<String, (Value, Instant)>,
+    cache: LruCache<String, (Value, Instant, usize)>,
     max_age: Duration,
+    max_bytes: Option<usize>,
+    bytes: usize,
 }
 
 impl Cache {
@@ -13,11 +15,30 @@ impl Cache {
         Cache {
             cache: LruCache::new(NonZeroUsize::new(max_size).unwrap()),
             max_age,
+            max_bytes: None,
+            bytes: 0,
         }
     }
 
+    pub fn with_byte_limit(max_bytes: usize, max_age: Duration) -> Self {
+        Cache {
+            cache: LruCache::unbounded(),
+            max_age,
+            max_bytes: Some(max_bytes),
+            bytes: 0,
+        }
+    }
+
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
+
+    pub fn set_max_age(&mut self, max_age: Duration) {
+        self.max_age = max_age;
+    }
+
     pub fn get(&mut self, key: &str) -> Option<&Value> {
-        if let Some((value, timestamp)) = self.cache.get_mut(key) {
+        if let Some((value, timestamp, _)) = self.cache.get_mut(key) {
             if timestamp.elapsed() <= self.max_age {
                 return Some(value);
             }
@@ -27,25 +48,145 @@ impl Cache {
 
     pub fn put(&mut self, key: String, value: Value) {
         let timestamp = Instant::now();
-        self.cache.put(key, (value, timestamp));
+        let size = serde_json::to_vec(&value).map_or(0, |bytes| bytes.len());
 
-        if self.cache.len() > self.cache.cap().get() {
-            self.cache.pop_lru();
+        self.bytes += size;
+        if let Some((_, (_, _, evicted))) = self.cache.push(key, (value, timestamp, size)) {
+            self.bytes = self.bytes.saturating_sub(evicted);
+        }
+
+        if let Some(max_bytes) = self.max_bytes {
+            while self.bytes > max_bytes {
+                match self.cache.pop_lru() {
+                    Some((_, (_, _, evicted))) => self.bytes = self.bytes.saturating_sub(evicted),
+                    None => break,
+                }
+            }
         }
     }
 
+    pub fn contains(&self, key: &str) -> bool {
+        self.cache
+            .peek(key)
+            .is_some_and(|(_, timestamp, _)| timestamp.elapsed() <= self.max_age)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache
+            .iter()
+            .filter(|(_, (_, timestamp, _))| timestamp.elapsed() <= self.max_age)
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.cache.clear();
+        self.bytes = 0;
+    }
+
     pub fn remove_expired_entries(&mut self) {
         let now = Instant::now();
 
         let expired_keys: Vec<String> = self
             .cache
             .iter()
-            .filter(|(_, (_, timestamp))| now.duration_since(*timestamp) > self.max_age)
+            .filter(|(_, (_, timestamp, _))| now.duration_since(*timestamp) > self.max_age)
             .map(|(key, _)| key.clone())
             .collect();
 
         for key in expired_keys {
-            self.cache.pop(&key);
+            if let Some((_, _, size)) = self.cache.pop(&key) {
+                self.bytes = self.bytes.saturating_sub(size);
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn evicts_least_recently_used_over_capacity() {
+        let mut cache = Cache::new(2, Duration::from_secs(60));
+        cache.put("a".to_string(), json!(1));
+        cache.put("b".to_string(), json!(2));
+
+        assert_eq!(cache.get("a"), Some(&json!(1)));
+        cache.put("c".to_string(), json!(3));
+
+        assert_eq!(cache.get("a"), Some(&json!(1)));
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("c"), Some(&json!(3)));
+    }
+
+    #[test]
+    fn len_and_contains_skip_expired_entries() {
+        let mut cache = Cache::new(10, Duration::from_secs(60));
+        assert!(cache.is_empty());
+
+        cache.put("a".to_string(), json!(1));
+        cache.put("b".to_string(), json!(2));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.contains("a"));
+        assert!(!cache.contains("z"));
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert!(!cache.contains("a"));
+
+        let mut cache = Cache::new(10, Duration::ZERO);
+        cache.put("a".to_string(), json!(1));
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(cache.len(), 0);
+        assert!(!cache.contains("a"));
+    }
+
+    #[test]
+    fn evicts_down_to_byte_budget() {
+        let mut cache = Cache::with_byte_limit(10, Duration::from_secs(60));
+        cache.put("a".to_string(), json!("1234"));
+        cache.put("b".to_string(), json!("5678"));
+
+        assert_eq!(cache.get("a"), None);
+        assert_eq!(cache.get("b"), Some(&json!("5678")));
+        assert_eq!(cache.bytes, 6);
+    }
+
+    #[test]
+    fn replacing_a_key_subtracts_its_old_size() {
+        let mut cache = Cache::with_byte_limit(10, Duration::from_secs(60));
+        cache.put("a".to_string(), json!("1234"));
+        cache.put("a".to_string(), json!("12"));
+
+        assert_eq!(cache.get("a"), Some(&json!("12")));
+        assert_eq!(cache.bytes, 4);
+    }
+
+    #[test]
+    fn value_larger_than_budget_is_not_kept() {
+        let mut cache = Cache::with_byte_limit(10, Duration::from_secs(60));
+        cache.put("a".to_string(), json!("1234"));
+        cache.put("b".to_string(), json!("12345678901"));
+
+        assert!(cache.cache.is_empty());
+        assert_eq!(cache.bytes, 0);
+    }
+
+    #[test]
+    fn expiry_keeps_byte_count_consistent() {
+        let mut cache = Cache::with_byte_limit(10, Duration::ZERO);
+        cache.put("a".to_string(), json!("1234"));
+        std::thread::sleep(Duration::from_millis(5));
+        cache.remove_expired_entries();
+
+        assert_eq!(cache.bytes, 0);
+
+        cache.put("b".to_string(), json!("5678"));
+        assert_eq!(cache.bytes, 6);
+    }
+}
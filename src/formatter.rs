@@ -1,6 +1,6 @@
 use ansi_term::Colour;
 use json_to_table::json_to_table;
-use reqwest::{blocking::Response, StatusCode};
+use reqwest::{header::HeaderMap, StatusCode, Url, Version};
 use serde_json::Value;
 use std::time::Duration;
 use tabled::settings::{style::RawStyle, Color, Style};
@@ -12,6 +12,9 @@ pub struct Formatter {
     version: bool,
     header: bool,
     json: bool,
+    color: bool,
+    slow: Duration,
+    max_depth: Option<usize>,
     style: RawStyle,
 }
 
@@ -38,6 +41,9 @@ impl Formatter {
         Self {
             json,
             style,
+            color: true,
+            slow: Duration::from_secs(1),
+            max_depth: None,
             time: true,
             size: true,
             status: true,
@@ -46,9 +52,65 @@ impl Formatter {
         }
     }
 
-    pub fn metadata(&self, response: &Response) {
+    pub fn set_time(&mut self, time: bool) -> &mut Self {
+        self.time = time;
+        self
+    }
+
+    pub fn set_size(&mut self, size: bool) -> &mut Self {
+        self.size = size;
+        self
+    }
+
+    pub fn set_status(&mut self, status: bool) -> &mut Self {
+        self.status = status;
+        self
+    }
+
+    pub fn set_version(&mut self, version: bool) -> &mut Self {
+        self.version = version;
+        self
+    }
+
+    pub fn set_header(&mut self, header: bool) -> &mut Self {
+        self.header = header;
+        self
+    }
+
+    pub fn set_json(&mut self, json: bool) -> &mut Self {
+        self.json = json;
+        self
+    }
+
+    pub fn set_color(&mut self, color: bool) -> &mut Self {
+        self.color = color;
+        self
+    }
+
+    pub fn set_slow_threshold(&mut self, slow: Duration) -> &mut Self {
+        self.slow = slow;
+        self
+    }
+
+    pub fn set_max_depth(&mut self, max_depth: Option<usize>) -> &mut Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn paint(&self, style: impl Into<ansi_term::Style>, text: &str) -> String {
+        if self.color {
+            style.into().paint(text).to_string()
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn label(&self, label: &str) -> String {
+        self.paint(Colour::White.bold(), label)
+    }
+
+    pub fn metadata(&self, status: StatusCode, headers: &HeaderMap, version: Version, size: u64) {
         if self.status {
-            let status = response.status();
             let p = match status {
                 StatusCode::OK => "Success!",
                 StatusCode::NOT_FOUND => "Resource Not Found!",
@@ -59,55 +121,66 @@ impl Formatter {
 
             let s = status.to_string();
 
-            let status = match status.as_u16() {
-                200..=299 => Colour::Green.paint(s),
-                300..=399 => Colour::Cyan.paint(s),
-                400..=499 => Colour::Yellow.paint(s),
-                500..=599 => Colour::Red.paint(s),
-                _ => Colour::White.paint(s),
+            let colour = match status.as_u16() {
+                200..=299 => Colour::Green,
+                300..=399 => Colour::Cyan,
+                400..=499 => Colour::Yellow,
+                500..=599 => Colour::Red,
+                _ => Colour::White,
             };
+            let status = self.paint(colour, &s);
 
-            println!("{} {status} ({p})", Colour::White.bold().paint("Status:"));
+            println!("{} {status} ({p})", self.label("Status:"));
         }
 
         if self.size {
-            let size = match response.content_length() {
-                Some(size) => {
-                    if size >= 1 << 30 {
-                        format!("{:.2} GB", size as f64 / (1 << 30) as f64)
-                    } else if size >= 1 << 20 {
-                        format!("{:.2} MB", size as f64 / (1 << 20) as f64)
-                    } else if size >= 1 << 10 {
-                        format!("{:.2} KB", size as f64 / (1 << 10) as f64)
-                    } else {
-                        format!("{} bytes", size)
-                    }
-                }
-                None => "Unknown".to_string(),
+            let size = if size >= 1 << 30 {
+                format!("{:.2} GB", size as f64 / (1 << 30) as f64)
+            } else if size >= 1 << 20 {
+                format!("{:.2} MB", size as f64 / (1 << 20) as f64)
+            } else if size >= 1 << 10 {
+                format!("{:.2} KB", size as f64 / (1 << 10) as f64)
+            } else {
+                format!("{} bytes", size)
             };
 
-            println!("{} {size}", Colour::White.bold().paint("Response Size:"));
+            println!("{} {size}", self.label("Response Size:"));
         }
 
         if self.header {
-            println!("{}", Colour::White.bold().paint("Response Header:"));
+            println!("{}", self.label("Response Header:"));
 
-            for (name, value) in response.headers() {
+            for (name, value) in headers {
                 println!("{}: {:?}", name, value);
             }
         }
 
         if self.version {
-            println!(
-                "{} {:?}",
-                Colour::White.bold().paint("Version:"),
-                response.version()
-            );
+            println!("{} {:?}", self.label("Version:"), version);
+        }
+    }
+
+    pub fn final_url(&self, url: &str, final_url: &Url) {
+        let redirected = match Url::parse(url) {
+            Ok(url) => url != *final_url,
+            Err(_) => url != final_url.as_str(),
+        };
+
+        if redirected {
+            println!("{} {final_url}", self.label("Redirected To:"));
         }
     }
 
     pub fn time(&self, time: Duration) {
         if self.time {
+            let colour = if time >= self.slow {
+                Colour::Red
+            } else if time < Duration::from_millis(300) {
+                Colour::Green
+            } else {
+                Colour::Yellow
+            };
+
             let secs = time.as_secs();
             let millis = time.subsec_millis();
 
@@ -119,15 +192,32 @@ impl Formatter {
             } else {
                 format!("{millis} ms")
             };
+            let time = self.paint(colour, &time);
 
-            println!("{} {time}", Colour::White.bold().paint("Response Time:"));
+            println!("{} {time}", self.label("Response Time:"));
+        }
+    }
+
+    pub fn text(&self, text: &str) {
+        if text.trim().is_empty() {
+            println!("[INFO]: Empty response body.");
+        } else {
+            println!("{text}");
         }
     }
 
     pub fn response(&self, json: &Value) {
         if !self.json {
-            let style = self.style.clone();
-            println!("{}", json_to_table(json).with(style));
+            let style = if self.color {
+                self.style.clone()
+            } else {
+                RawStyle::from(Style::ascii())
+            };
+            let json = match self.max_depth {
+                Some(depth) => collapse(json, depth),
+                None => json.clone(),
+            };
+            println!("{}", json_to_table(&json).with(style));
         } else {
             match serde_json::to_string_pretty(&json) {
                 Ok(result) => println!("{result}"),
@@ -136,3 +226,17 @@ impl Formatter {
         }
     }
 }
+
+fn collapse(value: &Value, depth: usize) -> Value {
+    match value {
+        Value::Object(map) if depth == 0 && !map.is_empty() => Value::String("{…}".to_string()),
+        Value::Array(list) if depth == 0 && !list.is_empty() => Value::String("[…]".to_string()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| (k.clone(), collapse(v, depth - 1)))
+                .collect(),
+        ),
+        Value::Array(list) => Value::Array(list.iter().map(|v| collapse(v, depth - 1)).collect()),
+        _ => value.clone(),
+    }
+}
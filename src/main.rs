@@ -2,16 +2,17 @@ pub mod cache;
 pub mod formatter;
 pub mod session;
 
-use ansi_term::{enable_ansi_support, Colour};
+use ansi_term::Colour;
 use argh::FromArgs;
-use reqwest::{blocking::Client, header::HeaderMap};
+use reqwest::{header::HeaderMap, StatusCode, Url};
 use rustyline::{config::Configurer, error::ReadlineError, DefaultEditor};
 use serde_json::Value;
 use std::borrow::Cow;
 use std::io::Write;
-use std::time::Instant;
+use std::path::Path;
+use std::time::{Duration, Instant};
 
-use session::Session;
+use session::{RequestKey, Session};
 
 fn repl(session: &mut Session) {
     let mut rl = DefaultEditor::new().unwrap();
@@ -20,7 +21,12 @@ fn repl(session: &mut Session) {
         println!("[INFO]: No previous history.");
     }
 
-    if enable_ansi_support().is_err() {
+    if let Err(e) = session.load_history(Path::new("session.json")) {
+        println!("[ERROR]: {e}");
+    }
+
+    #[cfg(windows)]
+    if ansi_term::enable_ansi_support().is_err() {
         println!("[ERROR]: Your system doesn't support ansi_colors.");
     }
 
@@ -48,6 +54,13 @@ fn repl(session: &mut Session) {
                 match input {
                     "history" | "History" | "HISTORY" => session.show_history(),
                     "headers" | "Headers" | "HEADERS" => session.show_headers(),
+                    "cache" | "Cache" | "CACHE" => {
+                        println!("[INFO]: {} cached response(s).", session.cache.len());
+                    }
+                    "clear" | "Clear" | "CLEAR" => {
+                        session.cache.clear();
+                        println!("[INFO]: Cache cleared!");
+                    }
                     _ => {
                         process_input(input, session);
                     }
@@ -64,6 +77,12 @@ fn repl(session: &mut Session) {
         }
     }
     _ = rl.save_history("history.txt");
+
+    if !session.history.is_empty() {
+        if let Err(e) = session.save_history(Path::new("session.json")) {
+            println!("[ERROR]: {e}");
+        }
+    }
 }
 
 fn process_input(input: &str, session: &mut Session) {
@@ -88,6 +107,18 @@ fn process_input(input: &str, session: &mut Session) {
     let header = parts[1];
     let url = parts[2];
 
+    match Url::parse(url) {
+        Ok(x) if x.scheme() == "http" || x.scheme() == "https" => {}
+        Ok(x) => {
+            println!("[ERROR]: Unsupported URL scheme: {}", x.scheme());
+            return;
+        }
+        Err(e) => {
+            println!("[ERROR]: Invalid URL {url}: {e}");
+            return;
+        }
+    }
+
     match method {
         "GET" => send_request("GET", header, url, Cow::Borrowed(""), session),
         "POST" | "PUT" | "PATCH" => {
@@ -108,7 +139,8 @@ fn process_input(input: &str, session: &mut Session) {
 fn send_request(method: &str, header: &str, url: &str, body: Cow<str>, session: &mut Session) {
     session.cache.remove_expired_entries();
 
-    let cache_key = format!("{} {}", method, url);
+    let request_key = RequestKey::new(method, url, &body);
+    let cache_key = request_key.to_string();
 
     if let Some(cached_response) = session.cache.get(&cache_key) {
         println!("[INFO] Using cached response");
@@ -128,10 +160,13 @@ fn send_request(method: &str, header: &str, url: &str, body: Cow<str>, session:
         HeaderMap::new()
     };
 
-    let client = Client::builder()
-        .timeout(session.response_timeout)
-        .build()
-        .unwrap();
+    let client = match session.client() {
+        Ok(x) => x,
+        Err(e) => {
+            println!("[ERROR]: {e}");
+            return;
+        }
+    };
 
     let request = match method {
         "GET" => client.get(url),
@@ -154,72 +189,409 @@ fn send_request(method: &str, header: &str, url: &str, body: Cow<str>, session:
 
     match response {
         Ok(response) => {
-            session.formatter.metadata(&response);
-            session
-                .formatter
-                .time(Instant::now().duration_since(start_time));
+            let elapsed = Instant::now().duration_since(start_time);
+            let status = response.status();
+            let version = response.version();
+            let headers = response.headers().clone();
+            let final_url = response.url().clone();
+            let content_length = response.content_length();
+
+            let bytes = match response.bytes() {
+                Ok(x) => x,
+                Err(e) => {
+                    println!("[ERROR]: Failed to read response body: {e}");
+                    return;
+                }
+            };
+
+            let size = content_length.unwrap_or(bytes.len() as u64);
+
+            session.formatter.metadata(status, &headers, version, size);
+            session.formatter.final_url(url, &final_url);
+            session.formatter.time(elapsed);
+
+            if let Some(location) = unfollowed_redirect(session.max_redirects(), status, &headers) {
+                println!("[INFO]: Redirect not followed, Location: {location}");
+                return;
+            }
 
-            if response
-                .headers()
+            let content_type = headers
                 .get(reqwest::header::CONTENT_TYPE)
-                .and_then(|value| value.to_str().ok())
-                .unwrap_or("")
-                .contains("text/html")
-            {
-                let html = response.text().unwrap();
+                .and_then(|value| value.to_str().ok());
+
+            if content_type.unwrap_or("").contains("text/html") {
                 print!("[WARN]: Response is in HTML format.\nDo you want to print it? [y/n]: ");
                 std::io::stdout().flush().unwrap();
                 let mut body = String::new();
                 std::io::stdin().read_line(&mut body).unwrap();
 
                 if body.trim().eq_ignore_ascii_case("y") {
-                    println!("{}", html);
+                    println!("{}", String::from_utf8_lossy(&bytes));
                 }
 
                 return;
             }
 
-            let json: Value = response.json().unwrap();
-            session.history.insert(cache_key.clone(), json.clone());
-            session.cache.put(cache_key.clone(), json.clone());
-            session.formatter.response(&json);
+            match parse_body(content_type, &bytes) {
+                Body::Json(json) => {
+                    session.history.insert(request_key, json.clone());
+                    session.cache.put(cache_key, json.clone());
+                    session.formatter.response(&json);
+                }
+                Body::Text(text) => session.formatter.text(&text),
+                Body::Binary => {
+                    println!("[INFO]: Binary response body ({} bytes)", bytes.len());
+                }
+            }
         }
         Err(err) => {
-            let e = Colour::Red.dimmed().paint("[ERROR]");
-            let s = session.response_timeout.as_secs();
-
-            if err.is_timeout() {
-                println!("{e}: Response time exceeded the specified timeout of {s} seconds.");
-            } else if err.is_decode() {
-                println!("{e}: Failed to decode response.");
-            } else {
-                println!("{e}: {err}");
-            }
+            let e = session.formatter.paint(Colour::Red.dimmed(), "[ERROR]");
+            let message =
+                request_error(&err, session.response_timeout(), session.connect_timeout());
+
+            println!("{e}: {message}");
         }
     }
 }
 
+enum Body {
+    Json(Value),
+    Text(String),
+    Binary,
+}
+
+fn parse_body(content_type: Option<&str>, bytes: &[u8]) -> Body {
+    let text = || String::from_utf8_lossy(bytes).into_owned();
+
+    let Some(content_type) = content_type else {
+        // Without a Content-Type there's nothing to go on but the body itself.
+        return match serde_json::from_slice(bytes) {
+            Ok(json) => Body::Json(json),
+            Err(_) => match std::str::from_utf8(bytes) {
+                Ok(text) => Body::Text(text.to_string()),
+                Err(_) => Body::Binary,
+            },
+        };
+    };
+
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+
+    if mime == "application/json" || mime.ends_with("+json") {
+        match serde_json::from_slice(bytes) {
+            Ok(json) => Body::Json(json),
+            Err(_) => Body::Text(text()),
+        }
+    } else if mime.starts_with("text/") {
+        Body::Text(text())
+    } else {
+        Body::Binary
+    }
+}
+
+fn unfollowed_redirect(
+    max_redirects: usize,
+    status: StatusCode,
+    headers: &HeaderMap,
+) -> Option<&str> {
+    if max_redirects != 0 || !status.is_redirection() {
+        return None;
+    }
+
+    headers
+        .get(reqwest::header::LOCATION)
+        .and_then(|value| value.to_str().ok())
+}
+
+fn request_error(
+    err: &reqwest::Error,
+    response_timeout: Duration,
+    connect_timeout: Duration,
+) -> String {
+    let s = response_timeout.as_secs();
+    let c = connect_timeout.as_secs();
+
+    if err.is_connect() && err.is_timeout() {
+        format!(
+            "Connection wasn't established within the specified connect timeout of {c} seconds."
+        )
+    } else if err.is_timeout() {
+        format!("Request exceeded the specified total timeout of {s} seconds.")
+    } else if err.is_decode() {
+        "Failed to decode response.".to_string()
+    } else {
+        err.to_string()
+    }
+}
+
 #[derive(FromArgs)]
 /// Simple command-line application that allows users to send HTTP requests and view the response, to test APIs.
 struct Args {
     #[argh(
         option,
         short = 't',
-        description = "response timeout in seconds (default: 30s)"
+        description = "total request timeout in seconds, connecting included (default: 30s)"
     )]
     response_timeout: Option<u64>,
+    #[argh(
+        option,
+        short = 'T',
+        description = "connect timeout in seconds, must be below -t to apply (default: 10s)"
+    )]
+    connect_timeout: Option<u64>,
     #[argh(option, short = 'c', description = "cache size (default: 100)")]
     cache_size: Option<usize>,
+    #[argh(
+        option,
+        short = 'b',
+        description = "caps the cache by response size in bytes instead of count"
+    )]
+    cache_bytes: Option<usize>,
+    #[argh(
+        option,
+        short = 'e',
+        description = "cache expiry in seconds (default: 5s)"
+    )]
+    cache_ttl: Option<u64>,
+    #[argh(
+        option,
+        short = 'r',
+        description = "redirects to follow, 0 to not follow any (default: 10)"
+    )]
+    max_redirects: Option<usize>,
     #[argh(switch, short = 'j', description = "outputs in JSON (default: false)")]
     json: bool,
+    #[argh(
+        option,
+        short = 'd',
+        description = "collapses nested objects and arrays beyond this depth in tables"
+    )]
+    depth: Option<usize>,
+    #[argh(
+        switch,
+        short = 'H',
+        description = "shows response headers (default: false)"
+    )]
+    show_headers: bool,
+    #[argh(
+        switch,
+        short = 'V',
+        description = "shows HTTP version of response (default: false)"
+    )]
+    show_version: bool,
+    #[argh(
+        switch,
+        short = 'p',
+        description = "disables colored output, also set by a non-empty NO_COLOR (default: false)"
+    )]
+    plain: bool,
+    #[argh(
+        option,
+        short = 's',
+        description = "response time in ms from which it is shown as slow (default: 1000)"
+    )]
+    slow: Option<u64>,
+    #[argh(
+        option,
+        description = "PEM client certificate for mutual TLS, used with --key"
+    )]
+    cert: Option<String>,
+    #[argh(option, description = "PKCS#8 PEM private key for --cert")]
+    key: Option<String>,
+    #[argh(
+        option,
+        description = "proxy to send requests through (default: HTTP_PROXY/HTTPS_PROXY)"
+    )]
+    proxy: Option<String>,
 }
 
 fn main() {
     let args: Args = argh::from_env();
 
-    repl(&mut Session::new(
-        args.json,
-        args.response_timeout,
-        args.cache_size,
-    ));
+    let mut session = Session::new(args.json, args.response_timeout, args.cache_size);
+
+    if let Some(timeout) = args.connect_timeout {
+        session.set_connect_timeout(Duration::from_secs(timeout));
+    }
+
+    if let Some(max_redirects) = args.max_redirects {
+        session.set_max_redirects(max_redirects);
+    }
+
+    if let Some(ttl) = args.cache_ttl {
+        session.set_cache_ttl(Duration::from_secs(ttl));
+    }
+
+    if let Some(max_bytes) = args.cache_bytes {
+        session.set_cache_bytes(max_bytes);
+    }
+
+    session
+        .formatter
+        .set_max_depth(args.depth)
+        .set_header(args.show_headers)
+        .set_version(args.show_version)
+        .set_slow_threshold(Duration::from_millis(args.slow.unwrap_or(1000)))
+        .set_color(!args.plain && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()));
+
+    match (&args.cert, &args.key) {
+        (Some(cert), Some(key)) => {
+            if let Err(e) = session.set_client_cert(Path::new(cert), Path::new(key)) {
+                println!("[ERROR]: {e}");
+                return;
+            }
+        }
+        (None, None) => {}
+        _ => {
+            println!("[ERROR]: Both --cert and --key are needed for a client certificate!");
+            return;
+        }
+    }
+
+    if let Some(proxy) = &args.proxy {
+        if let Err(e) = session.set_proxy(proxy) {
+            println!("[ERROR]: {e}");
+            return;
+        }
+    }
+
+    repl(&mut session);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    fn redirecting_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request_line = String::new();
+                BufReader::new(&stream)
+                    .read_line(&mut request_line)
+                    .unwrap();
+
+                let response = if request_line.starts_with("GET /old ") {
+                    "HTTP/1.1 302 Found\r\nLocation: /new\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                } else {
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}"
+                };
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        format!("http://{addr}")
+    }
+
+    #[test]
+    fn body_is_parsed_by_content_type() {
+        let json = br#"{"id":1}"#;
+
+        assert!(matches!(
+            parse_body(Some("application/json; charset=utf-8"), json),
+            Body::Json(_)
+        ));
+        assert!(matches!(
+            parse_body(Some("application/problem+json"), json),
+            Body::Json(_)
+        ));
+        assert!(matches!(
+            parse_body(Some("text/plain"), json),
+            Body::Text(_)
+        ));
+        assert!(matches!(parse_body(Some("image/png"), json), Body::Binary));
+
+        assert!(matches!(parse_body(None, json), Body::Json(_)));
+        assert!(matches!(parse_body(None, b"hello"), Body::Text(_)));
+        assert!(matches!(parse_body(None, &[0xff, 0xfe]), Body::Binary));
+    }
+
+    #[test]
+    fn redirects_are_only_reported_when_not_followed() {
+        let base = redirecting_server();
+
+        let mut session = Session::new(false, None, None);
+        session.set_max_redirects(0);
+        let response = session
+            .client()
+            .unwrap()
+            .get(format!("{base}/old"))
+            .send()
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FOUND);
+        assert_eq!(
+            unfollowed_redirect(
+                session.max_redirects(),
+                response.status(),
+                response.headers()
+            ),
+            Some("/new")
+        );
+
+        let mut session = Session::new(false, None, None);
+        let response = session
+            .client()
+            .unwrap()
+            .get(format!("{base}/old"))
+            .send()
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.url().path(), "/new");
+        assert_eq!(
+            unfollowed_redirect(
+                session.max_redirects(),
+                response.status(),
+                response.headers()
+            ),
+            None
+        );
+
+        assert_eq!(
+            unfollowed_redirect(0, StatusCode::NOT_MODIFIED, &HeaderMap::new()),
+            None
+        );
+    }
+
+    #[test]
+    fn connect_timeout_is_reported_as_connect_timeout() {
+        // A listener that never accepts stops completing handshakes once its
+        // backlog is full, so any further connect hangs until it times out.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut backlog = Vec::new();
+        while backlog.len() < 1024 {
+            match TcpStream::connect_timeout(&addr, Duration::from_millis(100)) {
+                Ok(stream) => backlog.push(stream),
+                Err(_) => break,
+            }
+        }
+
+        let url = format!("http://{addr}/");
+
+        let mut session = Session::new(false, Some(5), None);
+        session.set_connect_timeout(Duration::from_secs(1));
+        let err = session.client().unwrap().get(&url).send().unwrap_err();
+        assert_eq!(
+            request_error(&err, session.response_timeout(), session.connect_timeout()),
+            "Connection wasn't established within the specified connect timeout of 1 seconds."
+        );
+
+        let mut session = Session::new(false, Some(1), None);
+        session.set_connect_timeout(Duration::from_secs(5));
+        let err = session.client().unwrap().get(&url).send().unwrap_err();
+        assert_eq!(
+            request_error(&err, session.response_timeout(), session.connect_timeout()),
+            "Request exceeded the specified total timeout of 1 seconds."
+        );
+    }
 }
@@ -1,16 +1,70 @@
 use crate::cache::Cache;
 use crate::formatter::Formatter;
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
-use serde_json::{from_str, Value};
-use std::collections::HashMap;
+use reqwest::{blocking::Client, redirect::Policy, Identity, Proxy};
+use serde_json::{from_str, json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
 use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RequestKey {
+    pub method: String,
+    pub url: String,
+    pub body_hash: Option<u64>,
+}
+
+impl RequestKey {
+    pub fn new(method: &str, url: &str, body: &str) -> Self {
+        let body_hash = if body.is_empty() {
+            None
+        } else {
+            Some(fnv1a(body.as_bytes()))
+        };
+
+        RequestKey {
+            method: method.to_string(),
+            url: url.to_string(),
+            body_hash,
+        }
+    }
+}
+
+// FNV-1a has a fixed definition, unlike DefaultHasher, so hashes written to
+// session.json keep matching new requests across Rust releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+impl fmt::Display for RequestKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} | {}", self.method, self.url)?;
+
+        if let Some(hash) = self.body_hash {
+            write!(f, " | body {hash:016x}")?;
+        }
+
+        Ok(())
+    }
+}
+
 pub struct Session {
     pub cache: Cache,
-    pub history: HashMap<String, Value>,
+    pub history: BTreeMap<RequestKey, Value>,
     pub formatter: Formatter,
-    pub response_timeout: Duration,
+    response_timeout: Duration,
+    connect_timeout: Duration,
+    max_redirects: usize,
+    identity: Option<Identity>,
+    proxy: Option<Proxy>,
+    client: Option<Client>,
+    unreadable_history: bool,
     headers: HashMap<String, String>,
 }
 
@@ -18,13 +72,49 @@ impl Session {
     pub fn new(json: bool, response_timeout: Option<u64>, cache_size: Option<usize>) -> Self {
         Session {
             cache: Cache::new(cache_size.unwrap_or(10), Duration::from_secs(5)),
-            history: HashMap::new(),
+            history: BTreeMap::new(),
             formatter: Formatter::new(json),
             response_timeout: Duration::from_secs(response_timeout.unwrap_or(30)),
+            connect_timeout: Duration::from_secs(10),
+            max_redirects: 10,
+            identity: None,
+            proxy: None,
+            client: None,
+            unreadable_history: false,
             headers: HashMap::new(),
         }
     }
 
+    pub fn response_timeout(&self) -> Duration {
+        self.response_timeout
+    }
+
+    pub fn connect_timeout(&self) -> Duration {
+        self.connect_timeout
+    }
+
+    pub fn set_connect_timeout(&mut self, timeout: Duration) {
+        self.connect_timeout = timeout;
+        self.client = None;
+    }
+
+    pub fn max_redirects(&self) -> usize {
+        self.max_redirects
+    }
+
+    pub fn set_max_redirects(&mut self, max_redirects: usize) {
+        self.max_redirects = max_redirects;
+        self.client = None;
+    }
+
+    pub fn set_cache_ttl(&mut self, ttl: Duration) {
+        self.cache.set_max_age(ttl);
+    }
+
+    pub fn set_cache_bytes(&mut self, max_bytes: usize) {
+        self.cache = Cache::with_byte_limit(max_bytes, self.cache.max_age());
+    }
+
     pub fn show_headers(&self) {
         if self.headers.is_empty() {
             println!("[INFO]: No HEADERS :(");
@@ -45,9 +135,128 @@ impl Session {
 
         println!("Session History:\n");
         for (request, response) in &self.history {
-            let pretty_request = request.replace(' ', " | ");
             let pretty_json = serde_json::to_string_pretty(&response).unwrap();
-            println!("Request: {pretty_request}\nResponse: {pretty_json}\n");
+            println!("Request: {request}\nResponse: {pretty_json}\n");
+        }
+    }
+
+    pub fn save_history(&mut self, path: &Path) -> Result<(), String> {
+        let entries: Vec<Value> = self
+            .history
+            .iter()
+            .map(|(request, response)| {
+                json!({
+                    "method": request.method,
+                    "url": request.url,
+                    "body_hash": request.body_hash,
+                    "response": response,
+                })
+            })
+            .collect();
+
+        let history = match serde_json::to_string(&entries) {
+            Ok(x) => x,
+            Err(e) => return Err(format!("While serializing history: {e}")),
+        };
+
+        if self.unreadable_history && path.exists() {
+            let backup = with_suffix(path, ".bak");
+            if let Err(e) = fs::rename(path, &backup) {
+                return Err(format!("While moving {} aside: {e}", path.display()));
+            }
+            self.unreadable_history = false;
+        }
+
+        let temp = with_suffix(path, ".tmp");
+        if let Err(e) = fs::write(&temp, history) {
+            return Err(format!("While writing {}: {e}", temp.display()));
+        }
+
+        match fs::rename(&temp, path) {
+            Ok(_) => Ok(()),
+            Err(e) => Err(format!("While writing {}: {e}", path.display())),
+        }
+    }
+
+    pub fn load_history(&mut self, path: &Path) -> Result<(), String> {
+        if !path.exists() {
+            return Ok(());
+        }
+
+        match read_history(path) {
+            Ok(x) => {
+                self.history.extend(x);
+                Ok(())
+            }
+            Err(e) => {
+                self.unreadable_history = true;
+                let backup = with_suffix(path, ".bak");
+                Err(format!("{e}, it will be kept as {}", backup.display()))
+            }
+        }
+    }
+
+    pub fn client(&mut self) -> Result<Client, String> {
+        if let Some(client) = &self.client {
+            return Ok(client.clone());
+        }
+
+        let redirect = match self.max_redirects {
+            0 => Policy::none(),
+            n => Policy::limited(n),
+        };
+
+        let mut builder = Client::builder()
+            .redirect(redirect)
+            .connect_timeout(self.connect_timeout)
+            .timeout(self.response_timeout);
+
+        if let Some(identity) = &self.identity {
+            builder = builder.identity(identity.clone());
+        }
+
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(proxy.clone());
+        }
+
+        match builder.build() {
+            Ok(x) => {
+                self.client = Some(x.clone());
+                Ok(x)
+            }
+            Err(e) => Err(format!("While building HTTP client: {e}")),
+        }
+    }
+
+    pub fn set_client_cert(&mut self, cert: &Path, key: &Path) -> Result<(), String> {
+        let cert = match fs::read(cert) {
+            Ok(x) => x,
+            Err(e) => return Err(format!("While reading {}: {e}", cert.display())),
+        };
+
+        let key = match fs::read(key) {
+            Ok(x) => x,
+            Err(e) => return Err(format!("While reading {}: {e}", key.display())),
+        };
+
+        match Identity::from_pkcs8_pem(&cert, &key) {
+            Ok(x) => {
+                self.identity = Some(x);
+                self.client = None;
+                Ok(())
+            }
+            Err(e) => Err(format!("Invalid client certificate or key: {e}")),
+        }
+    }
+
+    pub fn set_proxy(&mut self, url: &str) -> Result<(), String> {
+        match Proxy::all(url) {
+            Ok(x) => {
+                self.proxy = Some(x);
+                self.client = None;
+                Ok(())
+            }
+            Err(e) => Err(format!("Invalid proxy {url}: {e}")),
         }
     }
 
@@ -111,3 +320,104 @@ impl Session {
         Ok(headers)
     }
 }
+
+fn read_history(path: &Path) -> Result<Vec<(RequestKey, Value)>, String> {
+    let history = match fs::read_to_string(path) {
+        Ok(x) => x,
+        Err(e) => return Err(format!("While reading {}: {e}", path.display())),
+    };
+
+    let entries = match from_str::<Vec<Value>>(&history) {
+        Ok(x) => x,
+        Err(e) => return Err(format!("While parsing {}: {e}", path.display())),
+    };
+
+    let mut history = Vec::with_capacity(entries.len());
+
+    for entry in entries {
+        let (method, url) = match (entry["method"].as_str(), entry["url"].as_str()) {
+            (Some(method), Some(url)) => (method, url),
+            _ => return Err(format!("Malformed history entry in {}", path.display())),
+        };
+
+        let request = RequestKey {
+            method: method.to_string(),
+            url: url.to_string(),
+            body_hash: entry["body_hash"].as_u64(),
+        };
+
+        history.push((request, entry["response"].clone()));
+    }
+
+    Ok(history)
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(path);
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("might-{name}-{}.json", std::process::id()))
+    }
+
+    #[test]
+    fn body_hash_is_stable_and_distinguishes_bodies() {
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+
+        let first = RequestKey::new("POST", "http://a", r#"{"id":1}"#);
+        let second = RequestKey::new("POST", "http://a", r#"{"id":2}"#);
+        assert_ne!(first, second);
+        assert_ne!(first.to_string(), second.to_string());
+        assert_eq!(RequestKey::new("GET", "http://a", "").body_hash, None);
+    }
+
+    #[test]
+    fn malformed_history_is_not_partially_loaded() {
+        let path = temp_path("partial");
+        fs::write(
+            &path,
+            r#"[{"method":"GET","url":"http://a","body_hash":null,"response":1},{"url":"http://b"}]"#,
+        )
+        .unwrap();
+
+        let mut session = Session::new(false, None, None);
+        let result = session.load_history(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(result.is_err());
+        assert!(session.history.is_empty());
+    }
+
+    #[test]
+    fn malformed_history_survives_a_save() {
+        let path = temp_path("survives");
+        let backup = with_suffix(&path, ".bak");
+        fs::write(&path, "not json").unwrap();
+
+        let mut session = Session::new(false, None, None);
+        assert!(session.load_history(&path).is_err());
+
+        session
+            .history
+            .insert(RequestKey::new("GET", "http://a", ""), json!(1));
+        session.save_history(&path).unwrap();
+
+        let saved = fs::read_to_string(&path).unwrap();
+        let kept = fs::read_to_string(&backup).unwrap();
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&backup).unwrap();
+
+        assert_eq!(kept, "not json");
+        assert_eq!(
+            from_str::<Value>(&saved).unwrap(),
+            json!([{"method": "GET", "url": "http://a", "body_hash": null, "response": 1}])
+        );
+        assert!(!with_suffix(&path, ".tmp").exists());
+    }
+}